@@ -0,0 +1,72 @@
+//! Histogram diagnostics for [`super::binarize`] decisions.
+//!
+//! Ported from the calibration scripts used outside this crate: a quick log-scaled bar
+//! chart of the grayscale intensity distribution, with the chosen threshold marked, so
+//! it's obvious at a glance whether a scan is bimodal (binarizes cleanly either way),
+//! low-contrast (the peaks are close together and the cutoff sits in a near-empty
+//! valley), or background-dominated (one huge peak swallows everything else) -- and
+//! therefore whether `Binarize::Global` or `Binarize::Sauvola` is the better fit.
+
+use opencv::core::{Mat, Point, Size};
+use opencv::prelude::MatTraitConst;
+
+const BINS: usize = 256;
+const WIDTH: i32 = BINS as i32;
+const HEIGHT: i32 = 200;
+
+/// Computes the 256-bin grayscale intensity histogram of `image` (single-channel,
+/// 8-bit) and renders it as a log-scaled bar chart normalized to the tallest bin, with
+/// a vertical marker at `cutoff` if one is given -- there isn't a single global cutoff
+/// for `Binarize::AdaptiveGaussian`/`Binarize::Sauvola`, so callers pass `None` there.
+pub fn histogram(image: &Mat, cutoff: Option<f64>) -> opencv::Result<Mat> {
+    render(&counts(image)?, cutoff)
+}
+
+fn counts(image: &Mat) -> opencv::Result<[u32; BINS]> {
+    let size = image.size()?;
+    let mut counts = [0u32; BINS];
+
+    for y in 0..size.height {
+        for x in 0..size.width {
+            let pixel = *image.at_2d::<u8>(y, x)?;
+            counts[pixel as usize] += 1;
+        }
+    }
+
+    Ok(counts)
+}
+
+fn render(counts: &[u32; BINS], cutoff: Option<f64>) -> opencv::Result<Mat> {
+    let mut chart = Mat::new_size_with_default(
+        Size::new(WIDTH, HEIGHT),
+        opencv::core::CV_8UC3,
+        opencv::core::Scalar::all(255.0),
+    )?;
+
+    let max_count = *counts.iter().max().unwrap_or(&0) as f64;
+    let max_log = (1.0 + max_count).ln().max(f64::EPSILON);
+
+    for (bin, &count) in counts.iter().enumerate() {
+        let scaled = (1.0 + count as f64).ln() / max_log;
+        let bar_height = (scaled * (HEIGHT - 1) as f64).round() as i32;
+
+        opencv::imgproc::line_def(
+            &mut chart,
+            Point::new(bin as i32, HEIGHT - 1),
+            Point::new(bin as i32, HEIGHT - 1 - bar_height),
+            (0.0, 0.0, 0.0).into(),
+        )?;
+    }
+
+    if let Some(cutoff) = cutoff {
+        let x = cutoff.round().clamp(0.0, (BINS - 1) as f64) as i32;
+        opencv::imgproc::line_def(
+            &mut chart,
+            Point::new(x, 0),
+            Point::new(x, HEIGHT - 1),
+            (0.0, 0.0, 255.0).into(),
+        )?;
+    }
+
+    Ok(chart)
+}
@@ -0,0 +1,820 @@
+use average;
+use itertools::{Either, Itertools};
+use rayon::prelude::*;
+
+use opencv::{
+    self,
+    core::{Mat, Point, Point2f, Size},
+    prelude::{MatTrait, MatTraitConst},
+};
+
+pub mod histogram;
+pub mod stage;
+pub mod tuning;
+
+#[derive(Debug)]
+struct Poly(opencv::core::Vector<Point>);
+
+pub struct Rect {
+    top_left: Point,
+    top_right: Point,
+    bottom_left: Point,
+    bottom_right: Point,
+}
+
+impl Rect {
+    pub fn top(&self) -> Line {
+        Line {
+            p1: self.top_left,
+            p2: self.top_right,
+        }
+    }
+
+    pub fn right(&self) -> Line {
+        Line {
+            p1: self.top_right,
+            p2: self.bottom_right,
+        }
+    }
+
+    pub fn bottom(&self) -> Line {
+        Line {
+            p1: self.bottom_right,
+            p2: self.bottom_left,
+        }
+    }
+
+    pub fn left(&self) -> Line {
+        Line {
+            p1: self.bottom_left,
+            p2: self.top_left,
+        }
+    }
+}
+
+impl Into<opencv::core::Vector<Point>> for Rect {
+    fn into(self) -> opencv::core::Vector<Point> {
+        vec![
+            self.top_right,
+            self.top_left,
+            self.bottom_left,
+            self.bottom_right,
+        ]
+        .into()
+    }
+}
+
+impl Into<opencv::core::Vector<Point2f>> for Rect {
+    fn into(self) -> opencv::core::Vector<Point2f> {
+        vec![
+            Point2f {
+                x: self.top_right.x as f32,
+                y: self.top_right.y as f32,
+            },
+            Point2f {
+                x: self.top_left.x as f32,
+                y: self.top_left.y as f32,
+            },
+            Point2f {
+                x: self.bottom_left.x as f32,
+                y: self.bottom_left.y as f32,
+            },
+            Point2f {
+                x: self.bottom_right.x as f32,
+                y: self.bottom_right.y as f32,
+            },
+        ]
+        .into()
+    }
+}
+
+pub struct Line {
+    pub p1: Point,
+    pub p2: Point,
+}
+
+pub trait Geometry {
+    fn length(&self) -> f64;
+}
+
+impl Geometry for Point {
+    fn length(&self) -> f64 {
+        ((self.x * self.x + self.y * self.y) as f64).sqrt()
+    }
+}
+
+impl Geometry for Line {
+    fn length(&self) -> f64 {
+        let dx = self.p2.x - self.p1.x;
+        let dy = self.p2.y - self.p1.y;
+
+        ((dx * dx + dy * dy) as f64).sqrt()
+    }
+}
+
+impl Poly {
+    /// Sorts the points of the contour counter clockwise
+    pub fn order(&self) -> Option<opencv::core::Vector<Point>> {
+        let center = {
+            let l = self.0.len() as i32;
+            let points_sum = self.0.iter().reduce(|p1, p2| Point {
+                x: p1.x + p2.x,
+                y: p1.y + p2.y,
+            })?;
+            Point {
+                x: points_sum.x / l,
+                y: points_sum.y / l,
+            }
+        };
+
+        let points = self
+            .0
+            .iter()
+            .map(|p| Point {
+                x: p.x - center.x,
+                y: p.y - center.y,
+            })
+            .filter(|p| p.length() > 0.0);
+
+        let angles = points.clone().map(|p| ((p.y as f64) / p.length()).acos());
+
+        let mut pts_with_angles: Vec<_> = self.0.to_vec().iter().cloned().zip(angles).collect();
+        pts_with_angles.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+
+        let points: Vec<_> = pts_with_angles.iter().cloned().map(|p| p.0).collect();
+
+        Some(points.into())
+    }
+}
+
+impl std::cmp::PartialEq for Poly {
+    fn eq(&self, other: &Self) -> bool {
+        let self_area = opencv::imgproc::contour_area_def(&self.0).unwrap_or(f64::NAN);
+        let other_area = opencv::imgproc::contour_area_def(&other.0).unwrap_or(f64::NAN);
+
+        self_area == other_area
+    }
+}
+
+impl std::cmp::Eq for Poly {}
+
+impl std::cmp::PartialOrd for Poly {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        let self_area = opencv::imgproc::contour_area_def(&self.0).ok()?;
+        let other_area = opencv::imgproc::contour_area_def(&other.0).ok()?;
+
+        f64::partial_cmp(&self_area, &other_area)
+    }
+}
+
+impl std::cmp::Ord for Poly {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        let self_area = opencv::imgproc::contour_area_def(&self.0).unwrap_or(0.0);
+        let other_area = opencv::imgproc::contour_area_def(&other.0).unwrap_or(0.0);
+
+        f64::total_cmp(&self_area, &other_area)
+    }
+}
+
+/// Selects how a grayscale `Mat` is turned into a binary (foreground/background) image.
+///
+/// `Global` is a single Otsu cutoff over the whole image; it collapses on unevenly-lit
+/// scans. `Manual` is the same but with a fixed cutoff the caller picks instead of one
+/// Otsu computes, e.g. for interactively tuning against a known scan. `Sauvola`
+/// computes a per-pixel threshold from the local mean/stddev, which holds up much
+/// better on phone photos with uneven lighting.
+#[derive(Debug, Clone, Copy)]
+pub enum Binarize {
+    Global { cutoff: f64 },
+    Manual { cutoff: f64 },
+    AdaptiveGaussian,
+    Sauvola { window: i32, k: f64 },
+}
+
+impl Default for Binarize {
+    fn default() -> Self {
+        Binarize::Sauvola {
+            window: 25,
+            k: 0.34,
+        }
+    }
+}
+
+/// Binarizes `image` (must be single-channel grayscale) using `mode`: pixels above the
+/// threshold become 255 (background), pixels below become 0 (foreground). A pixel exactly
+/// at the threshold becomes 0 in the OpenCV-backed modes (`THRESH_BINARY` keeps only values
+/// strictly above it) and 255 under `Sauvola`.
+fn binarize(image: &Mat, mode: Binarize) -> opencv::Result<Mat> {
+    Ok(binarize_with_cutoff(image, mode)?.0)
+}
+
+/// Same as [`binarize`], but also returns the single global threshold actually used to
+/// separate foreground from background, where one exists: the value Otsu chose for
+/// `Global` (`opencv::imgproc::threshold` returns it, ignoring the `cutoff` passed in
+/// as a seed), the fixed `cutoff` for `Manual`, or `None` for the per-pixel modes
+/// (`AdaptiveGaussian`, `Sauvola`). Used by [`histogram`] to mark the chosen cutoff on
+/// the intensity histogram.
+fn binarize_with_cutoff(image: &Mat, mode: Binarize) -> opencv::Result<(Mat, Option<f64>)> {
+    let mut binary = Mat::default();
+
+    let cutoff = match mode {
+        Binarize::Global { cutoff } => Some(opencv::imgproc::threshold(
+            image,
+            &mut binary,
+            cutoff,
+            255.0,
+            opencv::imgproc::THRESH_OTSU,
+        )?),
+        Binarize::Manual { cutoff } => {
+            opencv::imgproc::threshold(
+                image,
+                &mut binary,
+                cutoff,
+                255.0,
+                opencv::imgproc::THRESH_BINARY,
+            )?;
+            Some(cutoff)
+        }
+        Binarize::AdaptiveGaussian => {
+            opencv::imgproc::adaptive_threshold(
+                image,
+                &mut binary,
+                255.0,
+                opencv::imgproc::ADAPTIVE_THRESH_GAUSSIAN_C,
+                opencv::imgproc::THRESH_BINARY,
+                11,
+                2.0,
+            )?;
+            None
+        }
+        Binarize::Sauvola { window, k } => {
+            binary = sauvola_threshold(image, window, k)?;
+            None
+        }
+    };
+
+    Ok((binary, cutoff))
+}
+
+/// Local adaptive binarization (Sauvola, 1999).
+///
+/// Builds an integral image of pixel values and one of squared pixel values, so the
+/// mean `m` and standard deviation `s` over any `window x window` neighbourhood can be
+/// read off in O(1). The per-pixel threshold is `T = m * (1 + k * (s/R - 1))`, with `R`
+/// the dynamic range of the standard deviation for 8-bit images (128) and `k` trading
+/// off sensitivity to local contrast. Windows are clamped at the image border rather
+/// than padded.
+fn sauvola_threshold(image: &Mat, window: i32, k: f64) -> opencv::Result<Mat> {
+    const R: f64 = 128.0;
+
+    let size = image.size()?;
+    let (width, height) = (size.width, size.height);
+    let half = window / 2;
+
+    let mut sum = Mat::default();
+    let mut sqsum = Mat::default();
+    opencv::imgproc::integral2(
+        image,
+        &mut sum,
+        &mut sqsum,
+        opencv::core::CV_64F,
+        opencv::core::CV_64F,
+    )?;
+
+    let region_sum = |integral: &Mat, x1: i32, y1: i32, x2: i32, y2: i32| -> opencv::Result<f64> {
+        let a = *integral.at_2d::<f64>(y2 + 1, x2 + 1)?;
+        let b = *integral.at_2d::<f64>(y1, x2 + 1)?;
+        let c = *integral.at_2d::<f64>(y2 + 1, x1)?;
+        let d = *integral.at_2d::<f64>(y1, x1)?;
+        Ok(a - b - c + d)
+    };
+
+    let mut binary =
+        Mat::new_size_with_default(size, opencv::core::CV_8UC1, opencv::core::Scalar::all(0.0))?;
+
+    for y in 0..height {
+        let y1 = (y - half).max(0);
+        let y2 = (y + half).min(height - 1);
+
+        for x in 0..width {
+            let x1 = (x - half).max(0);
+            let x2 = (x + half).min(width - 1);
+
+            let count = ((x2 - x1 + 1) * (y2 - y1 + 1)) as f64;
+            let mean = region_sum(&sum, x1, y1, x2, y2)? / count;
+            let mean_sq = region_sum(&sqsum, x1, y1, x2, y2)? / count;
+            let variance = (mean_sq - mean * mean).max(0.0);
+            let std_dev = variance.sqrt();
+
+            let threshold = mean * (1.0 + k * (std_dev / R - 1.0));
+            let pixel = *image.at_2d::<u8>(y, x)? as f64;
+
+            *binary.at_2d_mut::<u8>(y, x)? = if pixel < threshold { 0 } else { 255 };
+        }
+    }
+
+    Ok(binary)
+}
+
+pub struct Deskewed {
+    pub image: Mat,
+    pub angle: f64,
+}
+
+/// Searches for the dominant text/line rotation in `image` and returns it rotated level.
+///
+/// Binarizes a working copy, then for candidate angles in -15.0..=15.0 degrees (0.5
+/// degree steps) rotates the binary image and scores it by the variance of its
+/// horizontal projection (row sums of foreground pixels) -- sharp peaks mean the text
+/// rows are horizontal. The angle with the highest variance is then applied to the
+/// original image with `get_rotation_matrix_2d` + `warp_affine`. The detected angle is
+/// returned alongside the leveled image so callers can log it or reject pages rotated
+/// beyond some threshold.
+pub fn deskew(image: &Mat) -> opencv::Result<Deskewed> {
+    let binary = binarize(image, Binarize::default())?;
+    let mut inv = Mat::default();
+    opencv::core::bitwise_not_def(&binary, &mut inv)?;
+    let binary = inv;
+
+    let size = image.size()?;
+    let center = Point2f::new(size.width as f32 / 2.0, size.height as f32 / 2.0);
+
+    let mut best_angle = 0.0;
+    let mut best_score = f64::MIN;
+
+    let mut candidate = -15.0;
+    while candidate <= 15.0 {
+        let rotation = opencv::imgproc::get_rotation_matrix_2d(center, candidate, 1.0)?;
+
+        let mut rotated = Mat::default();
+        opencv::imgproc::warp_affine_def(&binary, &mut rotated, &rotation, size)?;
+
+        let score = horizontal_projection_variance(&rotated)?;
+        if score > best_score {
+            best_score = score;
+            best_angle = candidate;
+        }
+
+        candidate += 0.5;
+    }
+
+    let rotation = opencv::imgproc::get_rotation_matrix_2d(center, best_angle, 1.0)?;
+    let mut leveled = Mat::default();
+    // `warp_affine_def`'s default border is BORDER_CONSTANT filled with black, which
+    // would paint the rotated corner wedges solid black on a white-background scan --
+    // feeding bogus dark regions into the next denoise/binarize/table-extraction pass.
+    // Fill with white instead, matching the page background.
+    opencv::imgproc::warp_affine(
+        image,
+        &mut leveled,
+        &rotation,
+        size,
+        opencv::imgproc::INTER_LINEAR,
+        opencv::core::BORDER_CONSTANT,
+        opencv::core::Scalar::all(255.0),
+    )?;
+
+    Ok(Deskewed {
+        image: leveled,
+        angle: best_angle,
+    })
+}
+
+/// Variance of the per-row foreground pixel counts of a binary image, i.e. how peaked
+/// the horizontal projection is.
+fn horizontal_projection_variance(binary: &Mat) -> opencv::Result<f64> {
+    let size = binary.size()?;
+
+    let row_sums = (0..size.height)
+        .map(|y| -> opencv::Result<f64> {
+            let mut sum = 0.0;
+            for x in 0..size.width {
+                if *binary.at_2d::<u8>(y, x)? != 0 {
+                    sum += 1.0;
+                }
+            }
+            Ok(sum)
+        })
+        .collect::<opencv::Result<Vec<_>>>()?;
+
+    Ok(row_sums
+        .into_iter()
+        .collect::<average::Variance>()
+        .population_variance())
+}
+
+struct Table {
+    image: Mat,
+    rows: Vec<Vec<opencv::core::Rect>>,
+    /// Bounding boxes of the detected horizontal grid-line segments, sorted top to
+    /// bottom. The first and last are the table's actual top/bottom edges, used by
+    /// [`Table::rows_inside_table`] to build the "between lines" interior mask.
+    horizontal_lines: Vec<opencv::core::Rect>,
+}
+
+/// Tunable knobs for `Table::extract`'s horizontal/vertical line detection, previously
+/// hard-coded and only adjustable by editing the code and recompiling.
+#[derive(Debug, Clone, Copy)]
+pub struct TableParams {
+    /// `image width / horizontal_kernel_divisor` is the width of the horizontal line
+    /// structuring element.
+    pub horizontal_kernel_divisor: i32,
+    /// `image height / vertical_kernel_divisor` is the height of the vertical line
+    /// structuring element.
+    pub vertical_kernel_divisor: i32,
+    /// Number of morphological open iterations used to isolate the line masks.
+    pub morph_iterations: i32,
+    /// Row-batching tolerance, as a fraction of the mean box height.
+    pub row_tolerance: f64,
+}
+
+impl Default for TableParams {
+    fn default() -> Self {
+        TableParams {
+            horizontal_kernel_divisor: 50,
+            vertical_kernel_divisor: 35,
+            morph_iterations: 8,
+            row_tolerance: 0.5,
+        }
+    }
+}
+
+impl TryFrom<(Mat, Binarize)> for Table {
+    type Error = opencv::Error;
+    fn try_from((image, binarize_mode): (Mat, Binarize)) -> opencv::Result<Self> {
+        Table::extract(
+            image,
+            binarize_mode,
+            TableParams::default(),
+            |_name, _mat| Ok(()),
+        )
+    }
+}
+
+impl Table {
+    /// Same as `Table::try_from`, but takes explicit `params` and calls
+    /// `record(name, mat)` for every named intermediate along the way (the binarized
+    /// image, the detected horizontal and vertical line masks), so a `Pipeline` can
+    /// capture them for debugging.
+    fn extract(
+        image: Mat,
+        binarize_mode: Binarize,
+        params: TableParams,
+        mut record: impl FnMut(&str, &Mat) -> opencv::Result<()>,
+    ) -> opencv::Result<Self> {
+        let binary = binarize(&image, binarize_mode)?;
+        record("table_binary", &binary)?;
+
+        let mut inv = Mat::default();
+        opencv::core::bitwise_not_def(&binary, &mut inv)?;
+        let binary = inv;
+
+        let horizontal_kernel = opencv::imgproc::get_structuring_element_def(
+            opencv::imgproc::MORPH_RECT,
+            opencv::core::Size::new(image.size()?.width / params.horizontal_kernel_divisor, 1),
+        )?;
+
+        let vertical_kernel = opencv::imgproc::get_structuring_element_def(
+            opencv::imgproc::MORPH_RECT,
+            opencv::core::Size::new(1, image.size()?.height / params.vertical_kernel_divisor),
+        )?;
+
+        let vh_kernel = opencv::imgproc::get_structuring_element_def(
+            opencv::imgproc::MORPH_CROSS,
+            opencv::core::Size::new(3, 3),
+        )?;
+
+        let iterations = params.morph_iterations;
+
+        let mut binary_horizontal = Mat::default();
+        opencv::imgproc::morphology_ex(
+            &binary,
+            &mut binary_horizontal,
+            opencv::imgproc::MORPH_OPEN,
+            &horizontal_kernel,
+            opencv::core::Point::new(-1, -1),
+            iterations,
+            opencv::core::BORDER_CONSTANT,
+            opencv::imgproc::morphology_default_border_value()?,
+        )?;
+        record("horizontal_lines", &binary_horizontal)?;
+
+        let mut horizontal_line_contours: opencv::core::Vector<opencv::core::Vector<Point>> =
+            opencv::core::Vector::new();
+        opencv::imgproc::find_contours_def(
+            &binary_horizontal,
+            &mut horizontal_line_contours,
+            opencv::imgproc::RETR_EXTERNAL,
+            opencv::imgproc::CHAIN_APPROX_SIMPLE,
+        )?;
+        let horizontal_lines: Vec<_> = horizontal_line_contours
+            .into_iter()
+            .map(|c| opencv::imgproc::bounding_rect(&c))
+            .filter_map(opencv::Result::ok)
+            .sorted_by_key(|b| b.y)
+            .collect();
+
+        let mut binary_vertical = Mat::default();
+        opencv::imgproc::morphology_ex(
+            &binary,
+            &mut binary_vertical,
+            opencv::imgproc::MORPH_OPEN,
+            &vertical_kernel,
+            opencv::core::Point::new(-1, -1),
+            iterations,
+            opencv::core::BORDER_CONSTANT,
+            opencv::imgproc::morphology_default_border_value()?,
+        )?;
+        record("vertical_lines", &binary_vertical)?;
+
+        let mut vh_lines = Mat::default();
+        opencv::core::add_weighted_def(
+            &binary_vertical,
+            0.5,
+            &binary_horizontal,
+            0.5,
+            0.0,
+            &mut vh_lines,
+        )?;
+
+        let mut not_vh_lines = Mat::default();
+        opencv::core::bitwise_not_def(&vh_lines, &mut not_vh_lines)?;
+
+        let mut not_vh_lines_eroded = Mat::default();
+        opencv::imgproc::erode(
+            &not_vh_lines,
+            &mut not_vh_lines_eroded,
+            &vh_kernel,
+            opencv::core::Point::new(-1, -1),
+            2,
+            opencv::core::BORDER_CONSTANT,
+            opencv::imgproc::morphology_default_border_value()?,
+        )?;
+
+        let mut not_vh_lines_threshold = Mat::default();
+        opencv::imgproc::threshold(
+            &not_vh_lines_eroded,
+            &mut not_vh_lines_threshold,
+            128.0,
+            255.0,
+            opencv::imgproc::THRESH_OTSU | opencv::imgproc::THRESH_BINARY,
+        )?;
+
+        let mut contours: opencv::core::Vector<opencv::core::Vector<Point>> =
+            opencv::core::Vector::new();
+        opencv::imgproc::find_contours_def(
+            &not_vh_lines_threshold,
+            &mut contours,
+            opencv::imgproc::RETR_TREE,
+            opencv::imgproc::CHAIN_APPROX_SIMPLE,
+        )?;
+
+        let boxes: Vec<_> = contours
+            .into_iter()
+            .map(|c| opencv::imgproc::bounding_rect(&c))
+            .filter_map(opencv::Result::ok)
+            .sorted_by_key(|b| b.y)
+            .collect();
+
+        let mean_height = boxes
+            .iter()
+            .map(|b| b.height as f64)
+            .collect::<average::Mean>()
+            .mean();
+
+        let rows: Vec<_> = boxes
+            .into_iter()
+            .batching(|it| {
+                let current = it.clone().next()?;
+                let current_max_height = current.y as f64 + mean_height * params.row_tolerance;
+
+                // Iterate over boxes, starting with 'current'
+                let x = it
+                    .take_while(|b| b.y as f64 <= current_max_height)
+                    .sorted_by_key(|b| b.x)
+                    .collect::<Vec<_>>();
+
+                Some(x)
+            })
+            .collect();
+
+        Ok(Table {
+            image,
+            rows,
+            horizontal_lines,
+        })
+    }
+
+    /// Filters each detected row down to the cells that genuinely belong to the table
+    /// grid, using a "filter between lines" test: `Table::extract`'s row batching only
+    /// looks at `mean_height`, and the OCR cell filter only checked `width > 10 &&
+    /// height > 10`, so stray contours picked up outside the grid (page noise, torn
+    /// edges) leak through as bogus rows/cells.
+    ///
+    /// The table's actual top and bottom edges are the first and last detected
+    /// horizontal grid-line segments (`horizontal_lines`), not the image bounds --
+    /// page margins above/below the grid are genuinely outside the table. This builds
+    /// a filled-polygon mask of that interior and estimates a line height from the
+    /// vertical gap between those edges at the image's horizontal midpoint, divided
+    /// across the rows already found -- a cell is kept only if at least 88% of its
+    /// area falls inside the mask and its height is close to that estimate.
+    fn rows_inside_table(&self) -> opencv::Result<Vec<Vec<opencv::core::Rect>>> {
+        const MIN_AREA_INSIDE: f64 = 0.88;
+        const HEIGHT_TOLERANCE: f64 = 0.75;
+
+        let size = self.image.size()?;
+
+        let quad = match (self.horizontal_lines.first(), self.horizontal_lines.last()) {
+            (Some(top_line), Some(bottom_line)) if self.horizontal_lines.len() >= 2 => Rect {
+                top_left: Point::new(top_line.x, top_line.y),
+                top_right: Point::new(top_line.x + top_line.width, top_line.y),
+                bottom_left: Point::new(bottom_line.x, bottom_line.y + bottom_line.height),
+                bottom_right: Point::new(
+                    bottom_line.x + bottom_line.width,
+                    bottom_line.y + bottom_line.height,
+                ),
+            },
+            // Fewer than two horizontal grid lines were detected (e.g. a borderless
+            // table); fall back to the image bounds rather than rejecting every cell.
+            _ => Rect {
+                top_left: Point::new(0, 0),
+                top_right: Point::new(size.width - 1, 0),
+                bottom_left: Point::new(0, size.height - 1),
+                bottom_right: Point::new(size.width - 1, size.height - 1),
+            },
+        };
+
+        let mid_x = size.width / 2;
+        let top_edge = point_on_line_at_x(&quad.top(), mid_x);
+        let bottom_edge = point_on_line_at_x(&quad.bottom(), mid_x);
+        let table_height = (Line {
+            p1: top_edge,
+            p2: bottom_edge,
+        })
+        .length();
+        let expected_row_height = table_height / self.rows.len().max(1) as f64;
+
+        let mut mask =
+            Mat::new_size_with_default(size, opencv::core::CV_8UC1, opencv::core::Scalar::all(0.0))?;
+        let polygon: opencv::core::Vector<Point> = quad.into();
+        opencv::imgproc::fill_poly_def(
+            &mut mask,
+            &opencv::core::Vector::<opencv::core::Vector<Point>>::from_iter([polygon]),
+            (255.0).into(),
+        )?;
+
+        let rows = self
+            .rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .filter(|cell| {
+                        let height_ok = (cell.height as f64 - expected_row_height).abs()
+                            <= expected_row_height * HEIGHT_TOLERANCE;
+
+                        height_ok
+                            && area_fraction_inside(&mask, **cell).unwrap_or(0.0) >= MIN_AREA_INSIDE
+                    })
+                    .copied()
+                    .collect()
+            })
+            .collect();
+
+        Ok(rows)
+    }
+}
+
+/// Linearly interpolates the point on `line` at horizontal coordinate `x`.
+fn point_on_line_at_x(line: &Line, x: i32) -> Point {
+    let dx = line.p2.x - line.p1.x;
+    if dx == 0 {
+        return Point::new(x, line.p1.y);
+    }
+
+    let t = (x - line.p1.x) as f64 / dx as f64;
+    let y = line.p1.y as f64 + t * (line.p2.y - line.p1.y) as f64;
+    Point::new(x, y.round() as i32)
+}
+
+/// Fraction of `cell`'s area that falls inside the non-zero region of `mask`.
+fn area_fraction_inside(mask: &Mat, cell: opencv::core::Rect) -> opencv::Result<f64> {
+    let roi = Mat::roi(mask, cell)?;
+    let inside = opencv::core::count_non_zero(&roi)? as f64;
+    let total = (cell.width * cell.height).max(1) as f64;
+    Ok(inside / total)
+}
+
+pub fn imgproc_pipeline(image: Mat, binarize_mode: Binarize) -> opencv::Result<Mat> {
+    let ctx = stage::default_pipeline()
+        .with_capture_from_env()
+        .run(image, binarize_mode)?;
+    Ok(ctx.image)
+}
+
+/// Same as [`imgproc_pipeline`], but appends the OCR stage and returns the recognized
+/// cell text instead of the debug overlay. The OCR stage used to be unreachable dead
+/// code at the end of one 200-line function; now it's just another stage to opt into.
+pub fn ocr_pipeline(image: Mat, binarize_mode: Binarize) -> opencv::Result<Vec<String>> {
+    let mut pipeline = stage::default_pipeline();
+    pipeline.push(Box::new(stage::OcrStage));
+
+    let ctx = pipeline.with_capture_from_env().run(image, binarize_mode)?;
+    Ok(ctx.names)
+}
+
+/// Loads a previously captured, already perspective-warped and deskewed image from
+/// `path` and runs just the post-warp half of the pipeline (denoise, binarize,
+/// sharpen, table extraction). Pairs with [`Pipeline::with_capture`][stage::Pipeline],
+/// whose `deskew` dump is exactly the input this expects (the `perspective_warp` dump is
+/// recorded before deskewing), so cell-detection tuning can iterate without re-running
+/// the slow front end.
+pub fn table_pipeline_from_warped_image(
+    path: &str,
+    binarize_mode: Binarize,
+) -> opencv::Result<Mat> {
+    // `imread_def`'s default `IMREAD_COLOR` would force this back to a 3-channel BGR
+    // Mat, but everything from `GrayscaleStage` up to `TableExtractionStage`'s final
+    // `cvt_color_def` -- including the `perspective_warp`/`deskew` captures this
+    // function resumes from -- is single-channel grayscale.
+    let image = opencv::imgcodecs::imread(path, opencv::imgcodecs::IMREAD_GRAYSCALE)?;
+    let ctx = stage::table_only_pipeline()
+        .with_capture_from_env()
+        .run(image, binarize_mode)?;
+    Ok(ctx.image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uniform_image(width: i32, height: i32, value: u8) -> opencv::Result<Mat> {
+        Mat::new_size_with_default(
+            Size::new(width, height),
+            opencv::core::CV_8UC1,
+            opencv::core::Scalar::all(value as f64),
+        )
+    }
+
+    #[test]
+    fn sauvola_threshold_uniform_image_is_all_background() -> opencv::Result<()> {
+        // A flat image has zero local standard deviation everywhere, so the Sauvola
+        // threshold at every pixel is `mean * (1 - k)`, strictly below a bright uniform
+        // `mean` -- every pixel should come out as background (255).
+        let image = uniform_image(9, 9, 200)?;
+        let binary = sauvola_threshold(&image, 3, 0.2)?;
+
+        let size = binary.size()?;
+        for y in 0..size.height {
+            for x in 0..size.width {
+                assert_eq!(*binary.at_2d::<u8>(y, x)?, 255);
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn point_on_line_at_x_interpolates_a_sloped_line() {
+        let line = Line {
+            p1: Point::new(0, 0),
+            p2: Point::new(10, 20),
+        };
+
+        assert_eq!(point_on_line_at_x(&line, 5), Point::new(5, 10));
+    }
+
+    #[test]
+    fn point_on_line_at_x_handles_a_vertical_line() {
+        let line = Line {
+            p1: Point::new(3, 0),
+            p2: Point::new(3, 10),
+        };
+
+        assert_eq!(point_on_line_at_x(&line, 3), Point::new(3, 0));
+    }
+
+    #[test]
+    fn area_fraction_inside_full_and_zero_and_partial_overlap() -> opencv::Result<()> {
+        // A 10x10 mask, white (inside the table) only in its left half.
+        let mask = Mat::new_size_with_default(
+            Size::new(10, 10),
+            opencv::core::CV_8UC1,
+            opencv::core::Scalar::all(0.0),
+        )?;
+        let left_half = opencv::core::Rect::new(0, 0, 5, 10);
+        let mut roi = Mat::roi(&mask, left_half)?;
+        roi.set_to_def(&opencv::core::Scalar::all(255.0))?;
+
+        let fully_inside = opencv::core::Rect::new(1, 1, 3, 3);
+        assert_eq!(area_fraction_inside(&mask, fully_inside)?, 1.0);
+
+        let fully_outside = opencv::core::Rect::new(6, 1, 3, 3);
+        assert_eq!(area_fraction_inside(&mask, fully_outside)?, 0.0);
+
+        // Straddles the mask's inside/outside boundary at x=5: half in, half out.
+        let half_and_half = opencv::core::Rect::new(3, 0, 4, 10);
+        assert_eq!(area_fraction_inside(&mask, half_and_half)?, 0.5);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,591 @@
+//! Composable stages for the image processing pipeline.
+//!
+//! `imgproc_pipeline` used to be one 200-line function with deeply nested `let image =
+//! prepared;` shadowing and a hard-coded `return Ok(image)` that dead-coded the OCR
+//! section. Each phase is now a [`Stage`] that reads/writes a shared
+//! [`PipelineContext`], and a [`Pipeline`] just runs a `Vec` of them in order -- so
+//! stages can be enabled, disabled, or reordered without touching the others.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use opencv::{
+    core::{Mat, Point, Point2f, Size},
+    prelude::MatTraitConst,
+    text::BaseOCRTrait,
+};
+
+use super::{binarize_with_cutoff, deskew, histogram, Binarize, Poly, Rect, Table, TableParams};
+
+/// Env var checked by [`Pipeline::with_capture_from_env`].
+pub const CAPTURE_DIR_ENV_VAR: &str = "CV_CAPTURE_DIR";
+
+/// One phase of the image processing pipeline.
+pub trait Stage {
+    fn name(&self) -> &str;
+    fn apply(&self, ctx: &mut PipelineContext) -> opencv::Result<()>;
+}
+
+/// An ordered list of [`Stage`]s, run front to back over a [`PipelineContext`].
+pub struct Pipeline {
+    stages: Vec<Box<dyn Stage>>,
+    capture_dir: Option<PathBuf>,
+}
+
+impl Pipeline {
+    pub fn new(stages: Vec<Box<dyn Stage>>) -> Self {
+        Pipeline {
+            stages,
+            capture_dir: None,
+        }
+    }
+
+    pub fn push(&mut self, stage: Box<dyn Stage>) {
+        self.stages.push(stage);
+    }
+
+    /// Writes every named intermediate `Mat` recorded during `run` to `dir`, as
+    /// `<index>_<name>.png` in the order it was recorded.
+    pub fn with_capture(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.capture_dir = Some(dir.into());
+        self
+    }
+
+    /// Like [`Pipeline::with_capture`], but only enables capture if
+    /// [`CAPTURE_DIR_ENV_VAR`] is set, so a run directory can be turned on without
+    /// recompiling.
+    pub fn with_capture_from_env(self) -> Self {
+        match std::env::var(CAPTURE_DIR_ENV_VAR) {
+            Ok(dir) if !dir.is_empty() => self.with_capture(dir),
+            _ => self,
+        }
+    }
+
+    pub fn run(&self, image: Mat, binarize_mode: Binarize) -> opencv::Result<PipelineContext> {
+        let mut ctx = PipelineContext::new(image, binarize_mode);
+
+        if let Some(dir) = &self.capture_dir {
+            std::fs::create_dir_all(dir).map_err(|e| {
+                opencv::Error::new(1, format!("failed to create capture dir {dir:?}: {e}"))
+            })?;
+            ctx.capture = Some(Capture {
+                dir: dir.clone(),
+                next_index: 0,
+            });
+        }
+
+        for stage in &self.stages {
+            stage.apply(&mut ctx)?;
+        }
+
+        Ok(ctx)
+    }
+}
+
+/// The stages run for a plain scan-to-table pass, without OCR.
+pub fn default_pipeline() -> Pipeline {
+    Pipeline::new(vec![
+        Box::new(GrayscaleStage),
+        Box::new(BlurStage),
+        Box::new(BinarizeStage),
+        Box::new(DenoiseStage {
+            h: 11.0,
+            template_window_size: 31,
+            search_window_size: 9,
+        }),
+        Box::new(QuadDetectionStage::default()),
+        Box::new(PerspectiveWarpStage),
+        Box::new(DeskewStage),
+        Box::new(DenoiseStage {
+            h: 10.0,
+            template_window_size: 7,
+            search_window_size: 21,
+        }),
+        Box::new(BinarizeStage),
+        Box::new(SharpenStage),
+        Box::new(TableExtractionStage::default()),
+    ])
+}
+
+/// The post-warp half of [`default_pipeline`]: denoise, binarize, sharpen, table
+/// extraction. Meant to run on an already perspective-warped and deskewed image (e.g.
+/// one captured via [`Pipeline::with_capture`]) so cell-detection and OCR tuning can
+/// iterate without re-running the slow Canny/contour/warp front end.
+pub fn table_only_pipeline() -> Pipeline {
+    Pipeline::new(vec![
+        Box::new(DenoiseStage {
+            h: 10.0,
+            template_window_size: 7,
+            search_window_size: 21,
+        }),
+        Box::new(BinarizeStage),
+        Box::new(SharpenStage),
+        Box::new(TableExtractionStage::default()),
+    ])
+}
+
+struct Capture {
+    dir: PathBuf,
+    next_index: usize,
+}
+
+/// The working `Mat` plus everything stages hang off it as they run.
+pub struct PipelineContext {
+    pub image: Mat,
+    pub named: HashMap<String, Mat>,
+    pub binarize_mode: Binarize,
+    pub quad: Option<Rect>,
+    pub deskew_angle: Option<f64>,
+    pub table: Option<Table>,
+    pub names: Vec<String>,
+    capture: Option<Capture>,
+}
+
+impl PipelineContext {
+    pub fn new(image: Mat, binarize_mode: Binarize) -> Self {
+        PipelineContext {
+            image,
+            named: HashMap::new(),
+            binarize_mode,
+            quad: None,
+            deskew_angle: None,
+            table: None,
+            names: Vec::new(),
+            capture: None,
+        }
+    }
+
+    /// Stores a copy of `mat` as a named intermediate result, dumping it to the
+    /// capture directory (if one is set) as `<index>_<name>.png`.
+    pub fn record(&mut self, name: &str, mat: &Mat) -> opencv::Result<()> {
+        let mut copy = Mat::default();
+        mat.copy_to(&mut copy)?;
+
+        if let Some(capture) = &mut self.capture {
+            let path = capture.dir.join(format!("{:02}_{name}.png", capture.next_index));
+            capture.next_index += 1;
+            opencv::imgcodecs::imwrite_def(&path.to_string_lossy(), mat)?;
+        }
+
+        self.named.insert(name.to_owned(), copy);
+        Ok(())
+    }
+}
+
+pub struct GrayscaleStage;
+
+impl Stage for GrayscaleStage {
+    fn name(&self) -> &str {
+        "grayscale"
+    }
+
+    fn apply(&self, ctx: &mut PipelineContext) -> opencv::Result<()> {
+        let mut grayscale = Mat::default();
+        opencv::imgproc::cvt_color_def(
+            &ctx.image,
+            &mut grayscale,
+            opencv::imgproc::COLOR_BGR2GRAY,
+        )?;
+
+        ctx.record(self.name(), &grayscale)?;
+        ctx.image = grayscale;
+        Ok(())
+    }
+}
+
+pub struct BlurStage;
+
+impl Stage for BlurStage {
+    fn name(&self) -> &str {
+        "blur"
+    }
+
+    fn apply(&self, ctx: &mut PipelineContext) -> opencv::Result<()> {
+        let mut blurred = Mat::default();
+        opencv::imgproc::gaussian_blur(
+            &ctx.image,
+            &mut blurred,
+            Size::new(3, 3),
+            2.0,
+            0.0,
+            opencv::core::BORDER_DEFAULT,
+        )?;
+
+        ctx.record(self.name(), &blurred)?;
+        ctx.image = blurred;
+        Ok(())
+    }
+}
+
+/// Also records a `histogram` diagnostic of the image being binarized (log-scaled
+/// intensity bar chart, with the chosen cutoff marked) alongside the binary output, so
+/// a misbehaving binarization can be diagnosed from a capture dump.
+pub struct BinarizeStage;
+
+impl Stage for BinarizeStage {
+    fn name(&self) -> &str {
+        "binary"
+    }
+
+    fn apply(&self, ctx: &mut PipelineContext) -> opencv::Result<()> {
+        let (binary, cutoff) = binarize_with_cutoff(&ctx.image, ctx.binarize_mode)?;
+
+        let chart = histogram::histogram(&ctx.image, cutoff)?;
+        ctx.record("histogram", &chart)?;
+
+        ctx.record(self.name(), &binary)?;
+        ctx.image = binary;
+        Ok(())
+    }
+}
+
+pub struct DenoiseStage {
+    pub h: f32,
+    pub template_window_size: i32,
+    pub search_window_size: i32,
+}
+
+impl Stage for DenoiseStage {
+    fn name(&self) -> &str {
+        "denoise"
+    }
+
+    fn apply(&self, ctx: &mut PipelineContext) -> opencv::Result<()> {
+        let mut denoised = Mat::default();
+        opencv::photo::fast_nl_means_denoising(
+            &ctx.image,
+            &mut denoised,
+            self.h,
+            self.template_window_size,
+            self.search_window_size,
+        )?;
+
+        ctx.record(self.name(), &denoised)?;
+        ctx.image = denoised;
+        Ok(())
+    }
+}
+
+/// Finds the biggest 4-sided contour in the working image (the outer table quad).
+pub struct QuadDetectionStage {
+    pub canny_low: f64,
+    pub canny_high: f64,
+}
+
+impl Default for QuadDetectionStage {
+    fn default() -> Self {
+        QuadDetectionStage {
+            canny_low: 50.0,
+            canny_high: 150.0,
+        }
+    }
+}
+
+impl Stage for QuadDetectionStage {
+    fn name(&self) -> &str {
+        "quad"
+    }
+
+    fn apply(&self, ctx: &mut PipelineContext) -> opencv::Result<()> {
+        let mut edges = Mat::default();
+        opencv::imgproc::canny(&ctx.image, &mut edges, self.canny_low, self.canny_high, 3, false)?;
+
+        let mut contours: opencv::core::Vector<opencv::core::Vector<Point>> =
+            opencv::core::Vector::new();
+        opencv::imgproc::find_contours_def(
+            &edges,
+            &mut contours,
+            opencv::imgproc::RETR_EXTERNAL,
+            opencv::imgproc::CHAIN_APPROX_SIMPLE,
+        )?;
+
+        let biggest_rect: opencv::core::Vector<_> = contours
+            .iter()
+            .map(|c| -> opencv::Result<_> {
+                let mut hull: opencv::core::Vector<Point> = opencv::core::Vector::new();
+                opencv::imgproc::convex_hull_def(&c, &mut hull)?;
+
+                let mut poly = opencv::core::Vector::new();
+                opencv::imgproc::approx_poly_dp(
+                    &hull,
+                    &mut poly,
+                    0.001 * opencv::imgproc::arc_length(&hull, true)?,
+                    true,
+                )?;
+
+                Ok(poly)
+            })
+            .filter_map(Result::ok)
+            .filter(|c| c.len() == 4)
+            .map(Poly)
+            .max()
+            .ok_or(opencv::Error::new(1, "Could not get the biggest contour"))?
+            .order()
+            .ok_or(opencv::Error {
+                code: 1,
+                message: "Couldn't do thing".to_owned(),
+            })?;
+
+        // FIXME: Why is the order messed up here, it shouldn't be, but that's how it currently works
+        let biggest_rect = Rect {
+            top_left: biggest_rect.get(2)?,
+            top_right: biggest_rect.get(3)?,
+            bottom_right: biggest_rect.get(0)?,
+            bottom_left: biggest_rect.get(1)?,
+        };
+
+        ctx.quad = Some(biggest_rect);
+        Ok(())
+    }
+}
+
+pub struct PerspectiveWarpStage;
+
+impl Stage for PerspectiveWarpStage {
+    fn name(&self) -> &str {
+        "perspective_warp"
+    }
+
+    fn apply(&self, ctx: &mut PipelineContext) -> opencv::Result<()> {
+        let quad = ctx
+            .quad
+            .take()
+            .ok_or(opencv::Error::new(1, "No table quad detected"))?;
+
+        let max_height = f64::max(quad.left().length(), quad.right().length()) as f32;
+        let max_width = f64::max(quad.top().length(), quad.bottom().length()) as f32;
+
+        let dst = opencv::core::Vector::from_slice(&[
+            Point2f::new(0.0, 0.0),
+            Point2f::new(max_width - 1.0, 0.0),
+            Point2f::new(max_width - 1.0, max_height - 1.0),
+            Point2f::new(0.0, max_height - 1.0),
+        ]);
+
+        let transform = opencv::imgproc::get_perspective_transform(
+            &Into::<opencv::core::Vector<Point2f>>::into(quad),
+            &dst,
+            opencv::core::DECOMP_LU,
+        )?;
+
+        let mut warped = Mat::default();
+        opencv::imgproc::warp_perspective_def(
+            &ctx.image,
+            &mut warped,
+            &transform,
+            opencv::core::Size::new(max_width as i32, max_height as i32),
+        )?;
+
+        ctx.record(self.name(), &warped)?;
+        ctx.image = warped;
+        Ok(())
+    }
+}
+
+pub struct DeskewStage;
+
+impl Stage for DeskewStage {
+    fn name(&self) -> &str {
+        "deskew"
+    }
+
+    fn apply(&self, ctx: &mut PipelineContext) -> opencv::Result<()> {
+        let deskewed = deskew(&ctx.image)?;
+
+        ctx.record(self.name(), &deskewed.image)?;
+        ctx.deskew_angle = Some(deskewed.angle);
+        ctx.image = deskewed.image;
+        Ok(())
+    }
+}
+
+pub struct SharpenStage;
+
+impl Stage for SharpenStage {
+    fn name(&self) -> &str {
+        "sharpen"
+    }
+
+    fn apply(&self, ctx: &mut PipelineContext) -> opencv::Result<()> {
+        let kernel = Mat::from_slice_2d(&[[0, -1, 0], [-1, 5, -1], [0, -1, 0]])?;
+
+        let mut sharpened = Mat::default();
+        opencv::imgproc::filter_2d_def(&ctx.image, &mut sharpened, -1, &kernel)?;
+
+        ctx.record(self.name(), &sharpened)?;
+        ctx.image = sharpened;
+        Ok(())
+    }
+}
+
+/// Runs `Table::extract` on the working image and draws the detected cells as blue
+/// boxes (BGR `(255, 0, 0)`), the way the old hard-coded debug output did.
+pub struct TableExtractionStage {
+    pub params: TableParams,
+}
+
+impl Default for TableExtractionStage {
+    fn default() -> Self {
+        TableExtractionStage {
+            params: TableParams::default(),
+        }
+    }
+}
+
+impl Stage for TableExtractionStage {
+    fn name(&self) -> &str {
+        "table"
+    }
+
+    fn apply(&self, ctx: &mut PipelineContext) -> opencv::Result<()> {
+        let mut table_image = Mat::default();
+        ctx.image.copy_to(&mut table_image)?;
+
+        let binarize_mode = ctx.binarize_mode;
+        let table = Table::extract(table_image, binarize_mode, self.params, |name, mat| {
+            ctx.record(name, mat)
+        })?;
+
+        let mut overlay = Mat::default();
+        opencv::imgproc::cvt_color_def(&table.image, &mut overlay, opencv::imgproc::COLOR_GRAY2BGR)?;
+
+        for row in &table.rows {
+            for col in row {
+                opencv::imgproc::rectangle_def(&mut overlay, *col, (255.0, 0.0, 0.0).into())?;
+            }
+        }
+
+        ctx.record(self.name(), &overlay)?;
+        ctx.image = overlay;
+        ctx.table = Some(table);
+        Ok(())
+    }
+}
+
+/// Runs OCR over the cells of the previously detected [`Table`]. Opt-in: append this
+/// to a [`Pipeline`] after [`TableExtractionStage`] when recognized text is needed,
+/// rather than just the debug overlay.
+pub struct OcrStage;
+
+impl Stage for OcrStage {
+    fn name(&self) -> &str {
+        "ocr"
+    }
+
+    fn apply(&self, ctx: &mut PipelineContext) -> opencv::Result<()> {
+        let table = ctx
+            .table
+            .as_ref()
+            .ok_or(opencv::Error::new(1, "No table detected to OCR"))?;
+
+        let mut ocr = opencv::text::OCRTesseract::create(
+            "tessdata/",
+            "eng",
+            "",
+            opencv::text::OEM_DEFAULT,
+            opencv::text::PSM_AUTO,
+        )?;
+
+        let delta = 1;
+        let scaling = 2;
+
+        // Rows straight from `Table::extract` still include stray contours that
+        // happened to fall within `mean_height` of a real row but sit outside the
+        // table grid (page noise, torn edges); filter those out before picking
+        // columns to OCR.
+        let rows = table.rows_inside_table()?;
+
+        let first_min_x = rows
+            .first()
+            .and_then(|row| row.get(1))
+            .map(|cell| cell.x - 10)
+            .ok_or(opencv::Error::new(1, "No OCR candidate cells detected in the table"))?;
+
+        let interesting_rows = rows
+            .iter()
+            // FIXME: Bad way of handling unexpected columns
+            .filter(|row| row.len() >= 3);
+
+        let mut cells: Vec<Mat> = interesting_rows
+            .clone()
+            .map(|row| row[1])
+            .filter(|name_col| name_col.width > 10 && name_col.height > 10)
+            .map(|name_col| {
+                if name_col.x < first_min_x {
+                    opencv::core::Rect::new(
+                        name_col.x - delta,
+                        name_col.y - delta,
+                        name_col.width - delta,
+                        name_col.height - delta,
+                    )
+                } else {
+                    name_col
+                }
+            })
+            .map(|roi| -> opencv::Result<_> {
+                let img = Mat::roi(&table.image, roi)?;
+                Ok((img, roi))
+            })
+            .filter_map(opencv::Result::ok)
+            .map(|(img, roi)| -> opencv::Result<_> {
+                let new_size = opencv::core::Size::new(roi.width * scaling, roi.height * scaling);
+
+                let mut scaled = Mat::default();
+                opencv::imgproc::resize_def(&img, &mut scaled, new_size)?;
+
+                let mut closed = Mat::default();
+                let kernel = opencv::imgproc::get_structuring_element_def(
+                    opencv::imgproc::MORPH_CROSS,
+                    opencv::core::Size::new(3, 3),
+                )?;
+                opencv::imgproc::morphology_ex_def(
+                    &scaled,
+                    &mut closed,
+                    opencv::imgproc::MORPH_CLOSE,
+                    &kernel,
+                )?;
+
+                let mut thresholded = Mat::default();
+                let _ = opencv::imgproc::threshold(
+                    &closed,
+                    &mut thresholded,
+                    128.0,
+                    255.0,
+                    opencv::imgproc::THRESH_BINARY,
+                )?;
+
+                let delta = delta * scaling;
+                let thresholded = Mat::roi(
+                    &thresholded,
+                    opencv::core::Rect::new(
+                        delta,
+                        delta,
+                        thresholded.size()?.width - delta,
+                        thresholded.size()?.height - delta,
+                    ),
+                )?;
+
+                Ok(thresholded)
+            })
+            .filter_map(opencv::Result::ok)
+            .filter(|img| !img.empty())
+            .collect();
+
+        let names: Vec<_> = cells
+            .iter_mut()
+            .map(|img| -> opencv::Result<_> {
+                let mut name = String::default();
+                ocr.run_def(img, &mut name)?;
+                Ok(name)
+            })
+            .filter_map(opencv::Result::ok)
+            .collect();
+
+        for (i, cell) in cells.iter().enumerate() {
+            ctx.record(&format!("ocr_roi_{i}"), cell)?;
+        }
+
+        ctx.names = names;
+        Ok(())
+    }
+}
@@ -0,0 +1,125 @@
+//! Interactive trackbar window for tuning pipeline parameters.
+//!
+//! The pipeline has a handful of magic numbers (manual cutoff, denoise h/template/search,
+//! Canny thresholds, morphology iterations, kernel divisors, row-batching tolerance)
+//! that used to only be adjustable by editing `stage.rs`/`mod.rs` and recompiling. This
+//! opens a `highgui` window with trackbars bound to them, reruns the pipeline on the
+//! loaded image whenever a trackbar moves, and shows the detected cell rectangles (the
+//! same blue boxes `TableExtractionStage` draws) live.
+
+use opencv::core::Mat;
+use opencv::highgui;
+
+use super::stage::{
+    BinarizeStage, BlurStage, DenoiseStage, DeskewStage, GrayscaleStage, PerspectiveWarpStage,
+    Pipeline, QuadDetectionStage, SharpenStage, TableExtractionStage,
+};
+use super::{Binarize, TableParams};
+
+const WINDOW: &str = "yaac tuning";
+
+const MANUAL_CUTOFF: &str = "manual cutoff";
+const DENOISE_H: &str = "denoise h (x10)";
+const DENOISE_TEMPLATE_WINDOW: &str = "denoise template window";
+const DENOISE_SEARCH_WINDOW: &str = "denoise search window";
+const CANNY_LOW: &str = "canny low";
+const CANNY_HIGH: &str = "canny high";
+const MORPH_ITERATIONS: &str = "morph iterations";
+const H_KERNEL_DIVISOR: &str = "h kernel divisor";
+const V_KERNEL_DIVISOR: &str = "v kernel divisor";
+const ROW_TOLERANCE: &str = "row tolerance (x100)";
+
+/// Opens a trackbar-tuning window over `image` and blocks until a key is pressed.
+/// Useful for calibrating the pipeline against a new form layout interactively,
+/// instead of edit-compile-rerun.
+pub fn run(image: &Mat) -> opencv::Result<()> {
+    highgui::named_window(WINDOW, highgui::WINDOW_NORMAL)?;
+
+    create_trackbar(MANUAL_CUTOFF, 255, 128)?;
+    create_trackbar(DENOISE_H, 300, 110)?;
+    create_trackbar(DENOISE_TEMPLATE_WINDOW, 41, 31)?;
+    create_trackbar(DENOISE_SEARCH_WINDOW, 41, 9)?;
+    create_trackbar(CANNY_LOW, 300, 50)?;
+    create_trackbar(CANNY_HIGH, 300, 150)?;
+    create_trackbar(MORPH_ITERATIONS, 20, 8)?;
+    create_trackbar(H_KERNEL_DIVISOR, 200, 50)?;
+    create_trackbar(V_KERNEL_DIVISOR, 200, 35)?;
+    create_trackbar(ROW_TOLERANCE, 200, 50)?;
+
+    loop {
+        let preview = render(image)?;
+        highgui::imshow(WINDOW, &preview)?;
+
+        // -1 means no key was pressed within the timeout; any real key exits.
+        if highgui::wait_key(200)? >= 0 {
+            break;
+        }
+    }
+
+    highgui::destroy_window(WINDOW)?;
+    Ok(())
+}
+
+fn create_trackbar(name: &str, max: i32, initial: i32) -> opencv::Result<()> {
+    highgui::create_trackbar(name, WINDOW, None, max, None)?;
+    highgui::set_trackbar_pos(name, WINDOW, initial)?;
+    Ok(())
+}
+
+fn trackbar(name: &str) -> opencv::Result<i32> {
+    highgui::get_trackbar_pos(name, WINDOW)
+}
+
+/// Reads the current trackbar positions, runs them through a pipeline built from
+/// scratch, and returns the resulting debug overlay (detected cells drawn as blue
+/// boxes).
+fn render(image: &Mat) -> opencv::Result<Mat> {
+    let cutoff = trackbar(MANUAL_CUTOFF)? as f64;
+    let denoise_h = trackbar(DENOISE_H)? as f32 / 10.0;
+    let denoise_template_window = trackbar(DENOISE_TEMPLATE_WINDOW)?.max(1);
+    let denoise_search_window = trackbar(DENOISE_SEARCH_WINDOW)?.max(1);
+    let canny_low = trackbar(CANNY_LOW)? as f64;
+    let canny_high = trackbar(CANNY_HIGH)? as f64;
+    let morph_iterations = trackbar(MORPH_ITERATIONS)?.max(1);
+    let horizontal_kernel_divisor = trackbar(H_KERNEL_DIVISOR)?.max(1);
+    let vertical_kernel_divisor = trackbar(V_KERNEL_DIVISOR)?.max(1);
+    let row_tolerance = trackbar(ROW_TOLERANCE)? as f64 / 100.0;
+
+    let mut source = Mat::default();
+    image.copy_to(&mut source)?;
+
+    let pipeline = Pipeline::new(vec![
+        Box::new(GrayscaleStage),
+        Box::new(BlurStage),
+        Box::new(BinarizeStage),
+        Box::new(DenoiseStage {
+            h: denoise_h,
+            template_window_size: denoise_template_window,
+            search_window_size: denoise_search_window,
+        }),
+        Box::new(QuadDetectionStage {
+            canny_low,
+            canny_high,
+        }),
+        Box::new(PerspectiveWarpStage),
+        Box::new(DeskewStage),
+        Box::new(DenoiseStage {
+            h: denoise_h,
+            template_window_size: denoise_template_window,
+            search_window_size: denoise_search_window,
+        }),
+        Box::new(BinarizeStage),
+        Box::new(SharpenStage),
+        Box::new(TableExtractionStage {
+            params: TableParams {
+                horizontal_kernel_divisor,
+                vertical_kernel_divisor,
+                morph_iterations,
+                row_tolerance,
+            },
+        }),
+    ]);
+
+    let ctx = pipeline.run(source, Binarize::Manual { cutoff })?;
+    Ok(ctx.image)
+}
@@ -7,7 +7,7 @@ mod cv;
 
 fn main() -> opencv::Result<()> {
     let img = opencv::imgcodecs::imread_def("./img/list_2.jpg")?;
-    let img = cv::imgproc_pipeline(img)?;
+    let img = cv::imgproc_pipeline(img, cv::Binarize::default())?;
     let _ = opencv::imgcodecs::imwrite_def("./img/output.png", &img)?;
 
     // tauri::Builder::default()